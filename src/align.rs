@@ -0,0 +1,110 @@
+//! Lining up sibling nodes between two versions of a document.
+
+use std::{collections::HashMap, iter};
+
+use kdl::KdlNode;
+
+/// How the siblings on one side line up with the siblings in `base`.
+pub(crate) struct Alignment<'a> {
+    /// The node matching each node of `base`, unless it was removed.
+    pub(crate) matches: Vec<Option<&'a KdlNode>>,
+    /// The nodes added before each node of `base`, and after the last one.
+    pub(crate) added: Vec<Vec<&'a KdlNode>>,
+}
+
+/// Lines up `side` with `base`, first by the longest run of unchanged nodes
+/// and then by name among the rest.
+///
+/// Between unchanged nodes, changed ones are only paired by name
+/// if the number of nodes with that name stayed the same,
+/// as it's unclear otherwise which of them were removed or added.
+pub(crate) fn align<'a>(base: &[KdlNode], side: &'a [KdlNode]) -> Alignment<'a> {
+    let mut pairs = Vec::new();
+    let (mut b, mut s) = (0, 0);
+    let unchanged = lcs(base, side, |x, y| x == y);
+    for (i, j) in unchanged
+        .into_iter()
+        .chain(iter::once((base.len(), side.len())))
+    {
+        let (base_gap, side_gap) = (&base[b..i], &side[s..j]);
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for node in base_gap {
+            counts.entry(&node.name).or_default().0 += 1;
+        }
+        for node in side_gap {
+            counts.entry(&node.name).or_default().1 += 1;
+        }
+        let same = |x: &KdlNode, y: &KdlNode| {
+            x.name == y.name && matches!(counts.get(x.name.as_str()), Some((b, s)) if b == s)
+        };
+        pairs.extend(
+            lcs(base_gap, side_gap, same)
+                .into_iter()
+                .map(|(x, y)| (b + x, s + y)),
+        );
+        pairs.push((i, j));
+        b = i + 1;
+        s = j + 1;
+    }
+
+    let mut alignment = Alignment {
+        matches: vec![None; base.len()],
+        added: vec![Vec::new(); base.len() + 1],
+    };
+    let mut pairs = pairs.into_iter().peekable();
+    for (j, node) in side.iter().enumerate() {
+        let &(i, next) = pairs.peek().expect("the last pair is past the end");
+        if next == j {
+            alignment.matches[i] = Some(node);
+            pairs.next();
+        } else {
+            alignment.added[i].push(node);
+        }
+    }
+    alignment
+}
+
+/// Finds the longest common subsequence of `a` and `b`,
+/// returning the indices of the matching elements.
+///
+/// The common prefix and suffix are matched up front,
+/// so mostly unchanged inputs don't need the quadratic table.
+fn lcs<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> Vec<(usize, usize)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| eq(x, y)).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| eq(x, y))
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut pairs: Vec<_> = (0..prefix).map(|i| (i, i)).collect();
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        let mut lengths = vec![vec![0u32; b_mid.len() + 1]; a_mid.len() + 1];
+        for i in (0..a_mid.len()).rev() {
+            for j in (0..b_mid.len()).rev() {
+                lengths[i][j] = if eq(&a_mid[i], &b_mid[j]) {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a_mid.len() && j < b_mid.len() {
+            if eq(&a_mid[i], &b_mid[j]) {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    pairs
+}
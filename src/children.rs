@@ -0,0 +1,109 @@
+use std::cell::Cell;
+
+use serde::{
+    ser::{self, SerializeSeq},
+    Serialize, Serializer,
+};
+
+use crate::{node, value, Node};
+
+/// Serializes a [`Node`] whose children are produced lazily by an iterator,
+/// so they don't have to be collected into a `Vec<KdlNode>` first.
+///
+/// The node is serialized in the same shape as [`Node`],
+/// with its own children followed by the ones from the iterator.
+/// Children can be anything serializable in that shape,
+/// including [`Node`]s and other `SerializeChildren`.
+///
+/// As the iterator is consumed, a `SerializeChildren` can only be serialized once.
+///
+/// ```rust
+/// # use kdl::{KdlNode, KdlValue};
+/// # use serde_kdl::{Node, SerializeChildren};
+/// let root = Node(KdlNode { name: "items".to_string(), ..Default::default() });
+/// let items = (0..3).map(|i| {
+///     Node(KdlNode {
+///         name: "item".to_string(),
+///         values: vec![KdlValue::Int(i)],
+///         ..Default::default()
+///     })
+/// });
+///
+/// let mut json = Vec::new();
+/// serde_json::to_writer(&mut json, &SerializeChildren::new(root, items)).unwrap();
+///
+/// let node: Node = serde_json::from_slice(&json).unwrap();
+/// assert_eq!(node.0.children.len(), 3);
+/// assert_eq!(node.0.children[2].values, vec![KdlValue::Int(2)]);
+/// ```
+pub struct SerializeChildren<I> {
+    node: Node,
+    children: Cell<Option<I>>,
+}
+
+impl<I> SerializeChildren<I>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    /// Creates an adapter serializing `node` with `children` appended to its own.
+    pub fn new(node: Node, children: I) -> Self {
+        SerializeChildren {
+            node,
+            children: Cell::new(Some(children)),
+        }
+    }
+}
+
+impl<I> Serialize for SerializeChildren<I>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        node::serialize_node(
+            serializer,
+            &self.node.0.name,
+            &value::from_kdl_vec(self.node.0.values.clone()),
+            &value::from_kdl_map(self.node.0.properties.clone()),
+            &Children(self),
+        )
+    }
+}
+
+struct Children<'a, I>(&'a SerializeChildren<I>);
+
+impl<'a, I> Serialize for Children<'a, I>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let own = &self.0.node.0.children;
+        let streamed = self
+            .0
+            .children
+            .take()
+            .ok_or_else(|| ser::Error::custom("children can only be serialized once"))?
+            .into_iter();
+
+        let len = match streamed.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(own.len() + lower),
+            _ => None,
+        };
+        let mut seq = serializer.serialize_seq(len)?;
+        for child in own {
+            seq.serialize_element(&Node(child.clone()))?;
+        }
+        for child in streamed {
+            seq.serialize_element(&child)?;
+        }
+        seq.end()
+    }
+}
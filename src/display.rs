@@ -0,0 +1,45 @@
+//! Formatting shared by document diffs and merge conflicts.
+
+use std::collections::HashMap;
+
+use kdl::KdlValue;
+
+/// Gives paths to siblings like `server[1]/tls[0]`,
+/// where the index counts the preceding siblings with the same name.
+pub(crate) struct Siblings<'a> {
+    parent: &'a str,
+    counts: HashMap<String, usize>,
+}
+
+impl<'a> Siblings<'a> {
+    pub(crate) fn new(parent: &'a str) -> Self {
+        Siblings {
+            parent,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Returns the path of the next sibling named `name`.
+    pub(crate) fn path(&self, name: &str) -> String {
+        let index = self.counts.get(name).copied().unwrap_or(0);
+        if self.parent.is_empty() {
+            format!("{}[{}]", name, index)
+        } else {
+            format!("{}/{}[{}]", self.parent, name, index)
+        }
+    }
+
+    /// Counts a sibling named `name`, moving past it.
+    pub(crate) fn count(&mut self, name: &str) {
+        *self.counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Formats values the way they are written in a node, separated by spaces.
+pub(crate) fn values(values: &[KdlValue]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
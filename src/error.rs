@@ -0,0 +1,98 @@
+use std::fmt;
+
+use kdl::KdlValue;
+use serde::{de, ser};
+
+/// An error that occurred while decoding or building parts of a [`Node`](crate::Node).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    node: Option<String>,
+    msg: String,
+}
+
+impl Error {
+    /// Attaches the name of the node being decoded, unless one is already set.
+    pub(crate) fn in_node(mut self, name: &str) -> Self {
+        if self.node.is_none() {
+            self.node = Some(name.to_string());
+        }
+        self
+    }
+
+    /// Returns the name of the node this error occurred in, if known.
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.node {
+            Some(ref node) => write!(f, "node `{}`: {}", node, self.msg),
+            None => f.write_str(&self.msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            node: None,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        de::Error::custom(msg)
+    }
+}
+
+/// A recoverable issue found while decoding parts of a [`Node`](crate::Node).
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A property was ignored because the target type has no field for it.
+    UnknownProperty { node: String, property: String },
+    /// A number property was converted to a float type that can't hold it exactly.
+    LossyCoercion {
+        node: String,
+        property: String,
+        value: KdlValue,
+        target: String,
+    },
+    /// A node has a deprecated name.
+    DeprecatedNode { node: String, replacement: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnknownProperty { node, property } => {
+                write!(
+                    f,
+                    "node `{}`: unknown property `{}` ignored",
+                    node, property
+                )
+            }
+            Warning::LossyCoercion {
+                node,
+                property,
+                value,
+                target,
+            } => write!(
+                f,
+                "node `{}`: property `{}` value `{}` loses precision as `{}`",
+                node, property, value, target
+            ),
+            Warning::DeprecatedNode { node, replacement } => write!(
+                f,
+                "node `{}`: deprecated, use `{}` instead",
+                node, replacement
+            ),
+        }
+    }
+}
@@ -6,12 +6,37 @@
 //! Just wrap your `KdlNode`s and `KdlValue`s
 //! with `Node`s and `Value`s correspondingly.
 //!
+//! ## Features
+//!
+//! - `tracing`: emit [`tracing`] spans and events while (de)serializing,
+//!   decoding and converting nodes. Node spans record the name of the node
+//!   and its `path` from the outermost node, like `config/server/tls`.
+//! - `testing`: the `assert_kdl_eq!` macro and the `testing` module
+//!   for comparing KDL documents in tests.
+//! - `xml`: the `xml` module for converting between XML elements and KDL nodes.
+//!
 //! [Serde]: https://serde.rs
 //! [`kdl` crate]: https://crates.io/crates/kdl
 //! [KDL]: https://kdl.dev/
+//! [`tracing`]: https://crates.io/crates/tracing
 
+mod align;
+mod children;
+mod display;
+mod error;
+pub mod merge;
 mod node;
+pub mod redact;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
 mod value;
+pub mod with;
+#[cfg(feature = "xml")]
+pub mod xml;
 
-pub use node::Node;
+pub use children::SerializeChildren;
+pub use error::{Error, Warning};
+pub use node::{apply_defaults, Node};
 pub use value::Value;
@@ -0,0 +1,382 @@
+//! Three-way merging of KDL documents.
+//!
+//! The siblings on each side are lined up with the ones in `base`,
+//! first by the longest run of unchanged nodes and then by name among the rest.
+//! When the number of siblings with some name changed in between,
+//! it's unclear which of them were removed or added,
+//! so they all count as removed and re-added, like nodes that moved.
+//! Values merge as a whole, properties key by key, and children recursively.
+//!
+//! When both sides changed the same thing differently, `ours` is kept
+//! and a [`Conflict`] is recorded, so tools such as git merge drivers
+//! can report it and fail the merge.
+//!
+//! A node removed on one side conflicts with changes to it on the other:
+//!
+//! ```rust
+//! # use serde_kdl::merge;
+//! # fn doc(kdl: &str) -> Vec<kdl::KdlNode> { kdl::parse_document(kdl).unwrap() }
+//! let base = doc("server a=1 b=1; server a=2 b=1");
+//! let ours = doc("server a=2 b=1");
+//! let theirs = doc("server a=1 b=5; server a=2 b=1");
+//!
+//! let merge = merge::three_way(&base, &ours, &theirs);
+//! assert_eq!(merge.nodes, doc("server a=2 b=1"));
+//! assert_eq!(
+//!     merge.conflicts[0].to_string(),
+//!     "server[0]: node removed in ours but changed in theirs"
+//! );
+//!
+//! let merge = merge::three_way(&base, &ours, &ours);
+//! assert!(merge.is_clean());
+//! assert_eq!(merge.nodes, doc("server a=2 b=1"));
+//! ```
+//!
+//! Nodes added on both sides at the same place are paired by name
+//! and merged as if they were added to an empty node:
+//!
+//! ```rust
+//! # use serde_kdl::merge;
+//! # fn doc(kdl: &str) -> Vec<kdl::KdlNode> { kdl::parse_document(kdl).unwrap() }
+//! let base = doc("server");
+//! let ours = doc("server; tls port=443");
+//! let theirs = doc("server; tls port=8443; log");
+//!
+//! let merge = merge::three_way(&base, &ours, &theirs);
+//! assert_eq!(merge.nodes, doc("server; tls port=443; log"));
+//! assert_eq!(
+//!     merge.conflicts[0].to_string(),
+//!     "tls[0]: property `port` changed on both sides (base unset, ours `443`, theirs `8443`)"
+//! );
+//! ```
+//!
+//! Nodes moved in `ours` follow `ours`, nodes removed on both sides stay removed,
+//! and conflicts in children are found at any depth:
+//!
+//! ```rust
+//! # use serde_kdl::merge;
+//! # fn doc(kdl: &str) -> Vec<kdl::KdlNode> { kdl::parse_document(kdl).unwrap() }
+//! let base = doc("a 1; b 2; c 3");
+//! let merge = merge::three_way(&base, &doc("b 2; a 1"), &doc("a 1; b 5"));
+//! assert!(merge.is_clean());
+//! assert_eq!(merge.nodes, doc("b 5; a 1"));
+//!
+//! let base = doc("server { tls port=443; }");
+//! let ours = doc("server { tls port=8443; }");
+//! let theirs = doc("server { tls port=9443; }");
+//! let merge = merge::three_way(&base, &ours, &theirs);
+//! assert_eq!(
+//!     merge.conflicts[0].to_string(),
+//!     "server[0]/tls[0]: property `port` changed on both sides (base `443`, ours `8443`, theirs `9443`)"
+//! );
+//! ```
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use kdl::{KdlNode, KdlValue};
+
+use crate::{
+    align::align,
+    display::{self, Siblings},
+};
+
+/// The result of a merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    /// The merged nodes, preferring `ours` where there were conflicts.
+    pub nodes: Vec<KdlNode>,
+    /// Every conflict found, in document order.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Returns `true` if the merge had no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// A change made differently on both sides.
+///
+/// Paths look like `server[1]/tls[0]`,
+/// where the index counts the preceding siblings with the same name in the merged nodes.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Conflict {
+    /// A node was removed on one side and changed on the other.
+    Node {
+        path: String,
+        base: Option<KdlNode>,
+        ours: Option<KdlNode>,
+        theirs: Option<KdlNode>,
+    },
+    /// The values of a node were changed on both sides.
+    Values {
+        path: String,
+        base: Vec<KdlValue>,
+        ours: Vec<KdlValue>,
+        theirs: Vec<KdlValue>,
+    },
+    /// A property of a node was changed on both sides.
+    Property {
+        path: String,
+        key: String,
+        base: Option<KdlValue>,
+        ours: Option<KdlValue>,
+        theirs: Option<KdlValue>,
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::Node { path, ours, .. } => {
+                let (removed, changed) = if ours.is_some() {
+                    ("theirs", "ours")
+                } else {
+                    ("ours", "theirs")
+                };
+                write!(
+                    f,
+                    "{}: node removed in {} but changed in {}",
+                    path, removed, changed
+                )
+            }
+            Conflict::Values {
+                path,
+                base,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "{}: values changed on both sides (base `{}`, ours `{}`, theirs `{}`)",
+                path,
+                display::values(base),
+                display::values(ours),
+                display::values(theirs)
+            ),
+            Conflict::Property {
+                path,
+                key,
+                base,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "{}: property `{}` changed on both sides (base {}, ours {}, theirs {})",
+                path,
+                key,
+                display_property(base),
+                display_property(ours),
+                display_property(theirs)
+            ),
+        }
+    }
+}
+
+/// Merges the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// ```rust
+/// # use serde_kdl::merge;
+/// let base = kdl::parse_document("server port=80 host=\"a\"").unwrap();
+/// let ours = kdl::parse_document("server port=8080 host=\"a\"").unwrap();
+/// let theirs = kdl::parse_document("server port=80 host=\"b\"; tls").unwrap();
+///
+/// let merge = merge::three_way(&base, &ours, &theirs);
+/// assert!(merge.is_clean());
+/// assert_eq!(merge.nodes, kdl::parse_document("server port=8080 host=\"b\"; tls").unwrap());
+///
+/// let theirs = kdl::parse_document("server port=443 host=\"a\"").unwrap();
+/// let merge = merge::three_way(&base, &ours, &theirs);
+/// assert_eq!(
+///     merge.conflicts[0].to_string(),
+///     "server[0]: property `port` changed on both sides (base `80`, ours `8080`, theirs `443`)"
+/// );
+/// ```
+pub fn three_way(base: &[KdlNode], ours: &[KdlNode], theirs: &[KdlNode]) -> Merge {
+    let mut conflicts = Vec::new();
+    let nodes = merge_children("", base, ours, theirs, &mut conflicts);
+    Merge { nodes, conflicts }
+}
+
+fn merge_children(
+    parent: &str,
+    base: &[KdlNode],
+    ours: &[KdlNode],
+    theirs: &[KdlNode],
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<KdlNode> {
+    let (ours, theirs) = (align(base, ours), align(base, theirs));
+
+    let mut merged = Vec::new();
+    let mut siblings = Siblings::new(parent);
+    for i in 0..=base.len() {
+        merge_added(
+            &mut siblings,
+            &ours.added[i],
+            &theirs.added[i],
+            &mut merged,
+            conflicts,
+        );
+        if let Some(node) = base.get(i) {
+            let path = siblings.path(&node.name);
+            let (o, t) = (ours.matches[i], theirs.matches[i]);
+            if let Some(node) = merge_node(&path, Some(node), o, t, conflicts) {
+                siblings.count(&node.name);
+                merged.push(node);
+            }
+        }
+    }
+    merged
+}
+
+/// Merges nodes added at the same place, pairing them by name.
+fn merge_added(
+    siblings: &mut Siblings,
+    ours: &[&KdlNode],
+    theirs: &[&KdlNode],
+    merged: &mut Vec<KdlNode>,
+    conflicts: &mut Vec<Conflict>,
+) {
+    let (ours, theirs) = (keyed(ours), keyed(theirs));
+    let their_nodes: HashMap<_, _> = theirs.iter().copied().collect();
+    for (key, node) in &ours {
+        let path = siblings.path(key.0);
+        let other = their_nodes.get(key).copied();
+        if let Some(node) = merge_node(&path, None, Some(node), other, conflicts) {
+            siblings.count(&node.name);
+            merged.push(node);
+        }
+    }
+    let our_keys: HashSet<_> = ours.iter().map(|(key, _)| *key).collect();
+    for (key, node) in theirs {
+        if !our_keys.contains(&key) {
+            siblings.count(&node.name);
+            merged.push(node.clone());
+        }
+    }
+}
+
+/// Keys nodes by their name and their index among the nodes with that name.
+fn keyed<'a>(nodes: &[&'a KdlNode]) -> Vec<((&'a str, usize), &'a KdlNode)> {
+    let mut counts = HashMap::new();
+    nodes
+        .iter()
+        .map(|node| {
+            let index = counts.entry(node.name.as_str()).or_insert(0);
+            let key = (node.name.as_str(), *index);
+            *index += 1;
+            (key, *node)
+        })
+        .collect()
+}
+
+fn merge_node(
+    path: &str,
+    base: Option<&KdlNode>,
+    ours: Option<&KdlNode>,
+    theirs: Option<&KdlNode>,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<KdlNode> {
+    if let Some(node) = pick(&base, &ours, &theirs) {
+        return node.cloned();
+    }
+
+    let (ours, theirs) = match (ours, theirs) {
+        (Some(ours), Some(theirs)) => (ours, theirs),
+        _ => {
+            conflicts.push(Conflict::Node {
+                path: path.to_string(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            return ours.cloned();
+        }
+    };
+    // Nodes added on both sides are merged as if they were added to an empty node.
+    let empty = KdlNode {
+        name: ours.name.clone(),
+        ..Default::default()
+    };
+    let base = base.unwrap_or(&empty);
+
+    let values = match pick(&base.values, &ours.values, &theirs.values) {
+        Some(values) => values.clone(),
+        None => {
+            conflicts.push(Conflict::Values {
+                path: path.to_string(),
+                base: base.values.clone(),
+                ours: ours.values.clone(),
+                theirs: theirs.values.clone(),
+            });
+            ours.values.clone()
+        }
+    };
+
+    let keys: BTreeSet<_> = base
+        .properties
+        .keys()
+        .chain(ours.properties.keys())
+        .chain(theirs.properties.keys())
+        .collect();
+    let mut properties = ours.properties.clone();
+    for key in keys {
+        let (b, o, t) = (
+            base.properties.get(key),
+            ours.properties.get(key),
+            theirs.properties.get(key),
+        );
+        match pick(&b, &o, &t) {
+            Some(Some(value)) => {
+                properties.insert(key.clone(), (*value).clone());
+            }
+            Some(None) => {
+                properties.remove(key);
+            }
+            None => conflicts.push(Conflict::Property {
+                path: path.to_string(),
+                key: key.clone(),
+                base: b.cloned(),
+                ours: o.cloned(),
+                theirs: t.cloned(),
+            }),
+        }
+    }
+
+    let children = merge_children(
+        path,
+        &base.children,
+        &ours.children,
+        &theirs.children,
+        conflicts,
+    );
+
+    Some(KdlNode {
+        name: ours.name.clone(),
+        values,
+        properties,
+        children,
+    })
+}
+
+/// Picks the side that changed, or `None` if both changed differently.
+fn pick<'a, T: PartialEq>(base: &'a T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || base == theirs {
+        Some(ours)
+    } else if base == ours {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn display_property(value: &Option<KdlValue>) -> String {
+    match value {
+        Some(value) => format!("`{}`", value),
+        None => "unset".to_string(),
+    }
+}
@@ -1,13 +1,23 @@
-use std::{collections::HashMap, fmt};
+use std::{cell::RefCell, collections::HashMap, fmt, iter};
 
-use kdl::KdlNode;
+use kdl::{KdlNode, KdlValue};
 use serde::{
-    de::{self, MapAccess, SeqAccess, Visitor},
-    ser::SerializeStruct,
-    Deserialize, Deserializer, Serialize,
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+    ser::{self, Impossible, SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::{value, Value};
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::{
+    redact::{Path, Redacted, Redaction},
+    value, Error, Value, Warning,
+};
 
 /// A newtype wrapper for [`KdlNode`] with Serde support.
 ///
@@ -40,24 +50,818 @@ use crate::{value, Value};
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Node(pub KdlNode);
 
+impl Node {
+    /// Decodes the properties of this node into `T`,
+    /// ignoring its values and children.
+    ///
+    /// A struct field named `$name` receives the name of the node,
+    /// so it isn't lost when decoding a list of children.
+    /// Unit structs and `()` only decode from bare nodes without values,
+    /// properties or children, like flag nodes such as `enable-ipv6`.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// # use serde_kdl::Node;
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct Retry {
+    ///     count: u32,
+    ///     backoff: String,
+    /// }
+    ///
+    /// let nodes = kdl::parse_document(r#"retry 1 count=3 backoff="exp""#).unwrap();
+    /// let retry: Retry = Node(nodes[0].clone()).decode_props().unwrap();
+    /// assert_eq!(retry, Retry { count: 3, backoff: "exp".to_string() });
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct Server {
+    ///     #[serde(rename = "$name")]
+    ///     name: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let nodes = kdl::parse_document("servers { alpha port=80; beta port=8080; }").unwrap();
+    /// let servers = nodes[0]
+    ///     .children
+    ///     .iter()
+    ///     .map(|child| Node(child.clone()).decode_props())
+    ///     .collect::<Result<Vec<Server>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(servers[1], Server { name: "beta".to_string(), port: 8080 });
+    ///
+    /// #[derive(Deserialize)]
+    /// struct EnableIpv6;
+    ///
+    /// let nodes = kdl::parse_document("enable-ipv6; enable-ipv6 on=false; enable-ipv6 false").unwrap();
+    /// assert!(Node(nodes[0].clone()).decode_props::<EnableIpv6>().is_ok());
+    /// assert!(Node(nodes[1].clone()).decode_props::<EnableIpv6>().is_err());
+    /// assert!(Node(nodes[2].clone()).decode_props::<EnableIpv6>().is_err());
+    /// ```
+    pub fn decode_props<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = trace::node_span!(DEBUG, "decode_props", Some(&self.0.name));
+
+        let deserializer = PropsDeserializer {
+            node: &self.0,
+            warnings: None,
+        };
+        T::deserialize(deserializer).map_err(|e| self.decode_error(e))
+    }
+
+    /// Like [`Node::decode_props`], but also reports properties
+    /// that `T` has no field for instead of silently ignoring them,
+    /// and numbers converted to a float type that can't hold them exactly.
+    ///
+    /// Deprecated node names are found with [`Node::check_deprecated`] instead,
+    /// as they concern whole documents rather than the properties of one node.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// # use serde_kdl::{Node, Warning};
+    /// #[derive(Deserialize)]
+    /// struct Retry {
+    ///     count: u32,
+    /// }
+    ///
+    /// let nodes = kdl::parse_document("retry count=3 cuont=5").unwrap();
+    /// let (retry, warnings) = Node(nodes[0].clone())
+    ///     .decode_props_with_warnings::<Retry>()
+    ///     .unwrap();
+    /// assert_eq!(retry.count, 3);
+    /// assert_eq!(
+    ///     warnings,
+    ///     vec![Warning::UnknownProperty {
+    ///         node: "retry".to_string(),
+    ///         property: "cuont".to_string(),
+    ///     }]
+    /// );
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Limits {
+    ///     ratio: f32,
+    /// }
+    ///
+    /// let nodes = kdl::parse_document("limits ratio=0.1").unwrap();
+    /// let (_, warnings) = Node(nodes[0].clone())
+    ///     .decode_props_with_warnings::<Limits>()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "node `limits`: property `ratio` value `0.1` loses precision as `f32`"
+    /// );
+    /// ```
+    pub fn decode_props_with_warnings<T>(&self) -> Result<(T, Vec<Warning>), Error>
+    where
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = trace::node_span!(DEBUG, "decode_props", Some(&self.0.name));
+
+        let warnings = RefCell::new(Vec::new());
+        let deserializer = PropsDeserializer {
+            node: &self.0,
+            warnings: Some(&warnings),
+        };
+        let props = T::deserialize(deserializer).map_err(|e| self.decode_error(e))?;
+        Ok((props, warnings.into_inner()))
+    }
+
+    /// Reports this node and its descendants that have a deprecated name,
+    /// given as pairs of a deprecated name and its replacement.
+    ///
+    /// ```rust
+    /// # use serde_kdl::Node;
+    /// let nodes = kdl::parse_document("server { ssl; listen 80; }").unwrap();
+    /// let warnings = Node(nodes[0].clone()).check_deprecated(&[("ssl", "tls")]);
+    /// assert_eq!(warnings[0].to_string(), "node `ssl`: deprecated, use `tls` instead");
+    /// ```
+    pub fn check_deprecated(&self, deprecated: &[(&str, &str)]) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        find_deprecated(&self.0, deprecated, &mut warnings);
+        warnings
+    }
+
+    /// Decodes the values of this node into `T`, usually a tuple or a tuple struct,
+    /// ignoring its properties and children.
+    ///
+    /// ```rust
+    /// # use serde_kdl::Node;
+    /// let nodes = kdl::parse_document(r#"listen "localhost" 8080 tls=true"#).unwrap();
+    /// let node = Node(nodes[0].clone());
+    /// let (host, port) = node.decode_args::<(String, u16)>().unwrap();
+    /// assert_eq!((host.as_str(), port), ("localhost", 8080));
+    ///
+    /// let err = node.decode_args::<(String,)>().unwrap_err();
+    /// assert_eq!(err.node(), Some("listen"));
+    /// ```
+    pub fn decode_args<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = trace::node_span!(DEBUG, "decode_args", Some(&self.0.name));
+
+        let values = value::from_kdl_vec(self.0.values.clone());
+        T::deserialize(SeqDeserializer::new(values.into_iter())).map_err(|e| self.decode_error(e))
+    }
+
+    /// Serializes `value` and sets it as the property `key` of this node,
+    /// replacing any previous value.
+    ///
+    /// ```rust
+    /// # use kdl::{KdlNode, KdlValue};
+    /// # use serde_kdl::Node;
+    /// let mut node = Node(KdlNode { name: "server".to_string(), ..Default::default() });
+    /// node.set_property("port", &8080u16).unwrap();
+    /// assert_eq!(node.0.properties["port"], KdlValue::Int(8080));
+    ///
+    /// let err = node.set_property("hosts", &vec!["a", "b"]).unwrap_err();
+    /// assert_eq!(err.node(), Some("server"));
+    /// assert!(err.to_string().starts_with("node `server`: property `hosts`: "));
+    /// ```
+    pub fn set_property<T>(&mut self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value::to_kdl_value(value).map_err(|e| {
+            <Error as ser::Error>::custom(format_args!("property `{}`: {}", key, e))
+                .in_node(&self.0.name)
+        })?;
+        self.0.properties.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Serializes `value` and appends it to the values of this node.
+    ///
+    /// ```rust
+    /// # use kdl::{KdlNode, KdlValue};
+    /// # use serde_kdl::Node;
+    /// let mut node = Node(KdlNode { name: "listen".to_string(), ..Default::default() });
+    /// node.push_argument("localhost").unwrap();
+    /// node.push_argument(&Some(8080)).unwrap();
+    /// assert_eq!(node.0.to_string(), r#"listen "localhost" 8080"#);
+    /// ```
+    pub fn push_argument<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value::to_kdl_value(value).map_err(|e| e.in_node(&self.0.name))?;
+        self.0.values.push(value);
+        Ok(())
+    }
+
+    /// Serializes `value` into the properties of a new child named `name`,
+    /// the reverse of [`Node::decode_props`].
+    ///
+    /// Unit structs and `()` become a child without any properties,
+    /// and `None` fields are left out, as [`Node::decode_props`] reads them back as `None`.
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// # use serde::Serialize;
+    /// # use serde_kdl::Node;
+    /// #[derive(Serialize)]
+    /// struct Tls {
+    ///     enabled: bool,
+    ///     cert: Option<String>,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct EnableIpv6;
+    ///
+    /// let mut node = Node(KdlNode { name: "server".to_string(), ..Default::default() });
+    /// node.push_child_from("tls", &Tls { enabled: true, cert: None }).unwrap();
+    /// node.push_child_from("enable-ipv6", &EnableIpv6).unwrap();
+    /// assert_eq!(
+    ///     node.0.to_string(),
+    ///     "server {\n    tls enabled=true\n    enable-ipv6\n}"
+    /// );
+    /// ```
+    pub fn push_child_from<T>(&mut self, name: &str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let properties = value
+            .serialize(PropsSerializer)
+            .map_err(|e| e.in_node(name))?;
+        self.0.children.push(KdlNode {
+            name: name.to_string(),
+            properties,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    /// Fills in whatever this node is missing from `defaults`,
+    /// before decoding it into a type that needs it.
+    ///
+    /// Properties are added if they aren't set, values are taken if this node has none,
+    /// and children are filled in with [`apply_defaults`].
+    ///
+    /// ```rust
+    /// # use serde_kdl::Node;
+    /// let defaults = kdl::parse_document("server 80 host=\"localhost\" tls=false").unwrap();
+    /// let nodes = kdl::parse_document("server tls=true").unwrap();
+    ///
+    /// let mut node = Node(nodes[0].clone());
+    /// node.apply_defaults(&defaults[0]);
+    /// let expected = kdl::parse_document("server 80 host=\"localhost\" tls=true").unwrap();
+    /// assert_eq!(node.0, expected[0]);
+    /// ```
+    pub fn apply_defaults(&mut self, defaults: &KdlNode) {
+        fill_defaults(&mut self.0, defaults);
+    }
+
+    /// Returns a view of this node that serializes like it,
+    /// but with every value passed through `hook` to be kept, masked or dropped.
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use serde_kdl::{redact::{Entry, Path, Redaction}, Node, Value};
+    /// fn hide_secrets(path: &Path, _value: &Value) -> Redaction {
+    ///     match path.entry {
+    ///         Entry::Property("password") | Entry::Property("token") => Redaction::Mask,
+    ///         _ => Redaction::Keep,
+    ///     }
+    /// }
+    ///
+    /// let nodes = kdl::parse_document(r#"db user="app" password="hunter2""#).unwrap();
+    /// let node = Node(nodes[0].clone());
+    /// let json = serde_json::to_value(node.redacted(&hide_secrets)).unwrap();
+    /// assert_eq!(json["properties"], json!({ "user": "app", "password": "***" }));
+    ///
+    /// let kdl = node.redacted(&hide_secrets).to_string();
+    /// assert_eq!(
+    ///     kdl::parse_document(&kdl).unwrap(),
+    ///     kdl::parse_document(r#"db user="app" password="***""#).unwrap()
+    /// );
+    /// ```
+    pub fn redacted<'a, F>(&'a self, hook: &'a F) -> Redacted<'a, F>
+    where
+        F: Fn(&Path, &Value) -> Redaction,
+    {
+        Redacted::new(self, hook)
+    }
+
+    fn decode_error(&self, error: Error) -> Error {
+        let error = error.in_node(&self.0.name);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%error, "failed to decode node");
+        error
+    }
+}
+
+/// The struct field that receives the node name in [`Node::decode_props`].
+const NAME_FIELD: &str = "$name";
+
+fn find_deprecated(node: &KdlNode, deprecated: &[(&str, &str)], warnings: &mut Vec<Warning>) {
+    if let Some((_, replacement)) = deprecated.iter().find(|(name, _)| *name == node.name) {
+        warnings.push(Warning::DeprecatedNode {
+            node: node.name.clone(),
+            replacement: replacement.to_string(),
+        });
+    }
+    for child in &node.children {
+        find_deprecated(child, deprecated, warnings);
+    }
+}
+
+/// Deserializes the properties of a node as a map.
+struct PropsDeserializer<'a> {
+    node: &'a KdlNode,
+    /// Collects recoverable issues, if set.
+    warnings: Option<&'a RefCell<Vec<Warning>>>,
+}
+
+impl<'a> PropsDeserializer<'a> {
+    fn properties(&self) -> impl Iterator<Item = (String, PropertyDeserializer<'a>)> {
+        let (node, warnings) = (self.node, self.warnings);
+        node.properties.iter().map(move |(key, value)| {
+            let value = PropertyDeserializer {
+                node,
+                key: key.clone(),
+                value: Value(value.clone()),
+                warnings,
+            };
+            (key.clone(), value)
+        })
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for PropsDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        MapDeserializer::new(self.properties()).deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let node = self.node;
+        if let Some(warnings) = self.warnings {
+            let mut unknown: Vec<_> = node
+                .properties
+                .keys()
+                .filter(|key| !fields.contains(&key.as_str()))
+                .collect();
+            unknown.sort();
+            let mut warnings = warnings.borrow_mut();
+            warnings.extend(unknown.into_iter().map(|key| Warning::UnknownProperty {
+                node: node.name.clone(),
+                property: key.clone(),
+            }));
+        }
+
+        if fields.contains(&NAME_FIELD) {
+            let name = PropertyDeserializer {
+                node,
+                key: NAME_FIELD.to_string(),
+                value: Value(KdlValue::String(node.name.clone())),
+                warnings: None,
+            };
+            let properties = iter::once((NAME_FIELD.to_string(), name)).chain(self.properties());
+            MapDeserializer::new(properties).deserialize_any(visitor)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let unexpected = if !self.node.properties.is_empty() {
+            de::Unexpected::Map
+        } else if !self.node.values.is_empty() {
+            de::Unexpected::Seq
+        } else if !self.node.children.is_empty() {
+            de::Unexpected::Other("node with children")
+        } else {
+            return visitor.visit_unit();
+        };
+        Err(de::Error::invalid_type(unexpected, &visitor))
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Deserializes the value of a property,
+/// reporting numbers that a float type can't hold exactly.
+struct PropertyDeserializer<'a> {
+    node: &'a KdlNode,
+    key: String,
+    value: Value,
+    warnings: Option<&'a RefCell<Vec<Warning>>>,
+}
+
+impl<'a> PropertyDeserializer<'a> {
+    fn check_lossy(&self, target: &str) {
+        let lossy = match (&self.value.0, target) {
+            (KdlValue::Float(f), "f32") => !f.is_nan() && f64::from(*f as f32) != *f,
+            (KdlValue::Int(i), "f32") => (*i as f32) as i128 != i128::from(*i),
+            (KdlValue::Int(i), "f64") => (*i as f64) as i128 != i128::from(*i),
+            _ => false,
+        };
+        if let (true, Some(warnings)) = (lossy, self.warnings) {
+            warnings.borrow_mut().push(Warning::LossyCoercion {
+                node: self.node.name.clone(),
+                property: self.key.clone(),
+                value: self.value.0.clone(),
+                target: target.to_string(),
+            });
+        }
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for PropertyDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.value.deserialize_any(visitor)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_lossy("f32");
+        self.value.deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_lossy("f64");
+        self.value.deserialize_f64(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.0 {
+            KdlValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for PropertyDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Serializes a struct or a map into the properties of a node.
+///
+/// A `$name` field is skipped, as the name is given separately,
+/// and so are `None` values, as a missing property decodes as `None`.
+struct PropsSerializer;
+
+impl PropsSerializer {
+    fn not_props(kind: &str) -> Error {
+        ser::Error::custom(format_args!(
+            "{} cannot be KDL properties, expected a struct or a map",
+            kind
+        ))
+    }
+}
+
+impl Serializer for PropsSerializer {
+    type Ok = HashMap<String, KdlValue>;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Self::Ok, Error>;
+    type SerializeTuple = Impossible<Self::Ok, Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = SerializeProps;
+    type SerializeStruct = SerializeProps;
+    type SerializeStructVariant = Impossible<Self::Ok, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a boolean"))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("an integer"))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("an integer"))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a float"))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a string"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a string"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a byte array"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("an option"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(HashMap::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        Err(Self::not_props("a unit variant"))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Self::not_props("a newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Self::not_props("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Self::not_props("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Self::not_props("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Self::not_props("a tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(SerializeProps::default())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Ok(SerializeProps::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Self::not_props("a struct variant"))
+    }
+}
+
+#[derive(Default)]
+struct SerializeProps {
+    properties: HashMap<String, KdlValue>,
+    key: Option<String>,
+}
+
+impl SerializeProps {
+    fn insert<T>(&mut self, key: String, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if key != NAME_FIELD {
+            let value = value::to_kdl_value(value)
+                .map_err(|e| ser::Error::custom(format_args!("property `{}`: {}", key, e)))?;
+            if value != KdlValue::Null {
+                self.properties.insert(key, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SerializeMap for SerializeProps {
+    type Ok = HashMap<String, KdlValue>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        match value::to_kdl_value(key)? {
+            KdlValue::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("property names must be strings")),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(self.properties)
+    }
+}
+
+impl SerializeStruct for SerializeProps {
+    type Ok = HashMap<String, KdlValue>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        Ok(self.properties)
+    }
+}
+
 impl Serialize for Node {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut node = serializer.serialize_struct("Node", 4)?;
-
-        node.serialize_field("name", &self.0.name)?;
-        let values = value::from_kdl_vec(self.0.values.clone());
-        node.serialize_field("values", &values)?;
-        let properties = value::from_kdl_map(self.0.properties.clone());
-        node.serialize_field("properties", &properties)?;
-        let children = from_kdl_vec(self.0.children.clone());
-        node.serialize_field("children", &children)?;
-        node.end()
+        serialize_node(
+            serializer,
+            &self.0.name,
+            &value::from_kdl_vec(self.0.values.clone()),
+            &value::from_kdl_map(self.0.properties.clone()),
+            &from_kdl_vec(self.0.children.clone()),
+        )
     }
 }
 
+/// Serializes a node named `name` in the shape of [`Node`],
+/// for the views of a node that serialize like it.
+pub(crate) fn serialize_node<S, V, P, C>(
+    serializer: S,
+    name: &str,
+    values: &V,
+    properties: &P,
+    children: &C,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: ?Sized + Serialize,
+    P: ?Sized + Serialize,
+    C: ?Sized + Serialize,
+{
+    #[cfg(feature = "tracing")]
+    let _span = trace::node_span!(TRACE, "serialize_node", Some(name));
+
+    let mut node = serializer.serialize_struct("Node", FIELDS.len())?;
+    node.serialize_field("name", name)?;
+    node.serialize_field("values", values)?;
+    node.serialize_field("properties", properties)?;
+    node.serialize_field("children", children)?;
+    node.end()
+}
+
 // TODO: use strum for codegen
 const FIELDS: &[&str] = &["name", "values", "properties", "children"];
 
@@ -118,9 +922,11 @@ impl<'de> Deserialize<'de> for Node {
             where
                 V: SeqAccess<'de>,
             {
-                let name = seq
+                let name: String = seq
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                #[cfg(feature = "tracing")]
+                trace::name_current_node(&name);
                 let values = seq
                     .next_element::<Vec<Value>>()?
                     .ok_or_else(|| de::Error::invalid_length(1, &self))?;
@@ -130,6 +936,8 @@ impl<'de> Deserialize<'de> for Node {
                 let children = seq
                     .next_element::<Vec<Node>>()?
                     .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(node = %name, "deserialized node");
                 Ok(Node(KdlNode {
                     name,
                     values: value::into_kdl_vec(values),
@@ -152,7 +960,10 @@ impl<'de> Deserialize<'de> for Node {
                             if name.is_some() {
                                 return Err(de::Error::duplicate_field("name"));
                             }
-                            name = Some(map.next_value()?);
+                            let value: String = map.next_value()?;
+                            #[cfg(feature = "tracing")]
+                            trace::name_current_node(&value);
+                            name = Some(value);
                         }
                         Field::Values => {
                             if values.is_some() {
@@ -179,6 +990,8 @@ impl<'de> Deserialize<'de> for Node {
                 let properties =
                     properties.ok_or_else(|| de::Error::missing_field("properties"))?;
                 let children = children.ok_or_else(|| de::Error::missing_field("children"))?;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(node = %name, "deserialized node");
                 Ok(Node(KdlNode {
                     name,
                     values: value::into_kdl_vec(values),
@@ -188,8 +1001,82 @@ impl<'de> Deserialize<'de> for Node {
             }
         }
 
-        deserializer.deserialize_struct("Node", FIELDS, NodeVisitor)
+        #[cfg(feature = "tracing")]
+        let _span = trace::node_span!(TRACE, "deserialize_node", None);
+
+        let node = deserializer.deserialize_struct("Node", FIELDS, NodeVisitor);
+        #[cfg(feature = "tracing")]
+        if let Err(error) = &node {
+            tracing::trace!(%error, "failed to deserialize node");
+        }
+        node
+    }
+}
+
+/// Fills in the nodes of a document from a document of defaults.
+///
+/// Each node with the same name as a default node gets its missing parts filled in
+/// with [`Node::apply_defaults`], so a single default `server` applies to every `server`.
+/// With several defaults of the same name, nodes use the one at the same position
+/// among nodes of that name, or the last one if there are more nodes than defaults.
+///
+/// Default nodes are only added when the document has no node of their name at all,
+/// so a list of default nodes is either added as a whole or not at all.
+///
+/// ```rust
+/// let defaults = kdl::parse_document("log level=\"info\"; server host=\"localhost\"").unwrap();
+/// let mut nodes = kdl::parse_document("server port=80; server port=81").unwrap();
+///
+/// serde_kdl::apply_defaults(&mut nodes, &defaults);
+/// assert_eq!(
+///     nodes,
+///     kdl::parse_document(
+///         "server port=80 host=\"localhost\"; server port=81 host=\"localhost\"; log level=\"info\""
+///     )
+///     .unwrap()
+/// );
+///
+/// let defaults = kdl::parse_document("peer \"a\"; peer \"b\"").unwrap();
+/// let mut nodes = kdl::parse_document("peer \"c\"").unwrap();
+/// serde_kdl::apply_defaults(&mut nodes, &defaults);
+/// assert_eq!(nodes, kdl::parse_document("peer \"c\"").unwrap());
+/// ```
+pub fn apply_defaults(nodes: &mut Vec<KdlNode>, defaults: &[KdlNode]) {
+    let mut by_name: HashMap<&str, Vec<&KdlNode>> = HashMap::new();
+    for default in defaults {
+        by_name.entry(&default.name).or_default().push(default);
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for node in nodes.iter_mut() {
+        if let Some(same_name) = by_name.get(node.name.as_str()) {
+            let index = counts.entry(node.name.clone()).or_insert(0);
+            let default = same_name
+                .get(*index)
+                .unwrap_or(&same_name[same_name.len() - 1]);
+            *index += 1;
+            fill_defaults(node, default);
+        }
+    }
+
+    let missing = defaults
+        .iter()
+        .filter(|default| !counts.contains_key(&default.name))
+        .cloned()
+        .collect::<Vec<_>>();
+    nodes.extend(missing);
+}
+
+fn fill_defaults(node: &mut KdlNode, defaults: &KdlNode) {
+    if node.values.is_empty() {
+        node.values = defaults.values.clone();
+    }
+    for (key, value) in &defaults.properties {
+        node.properties
+            .entry(key.clone())
+            .or_insert_with(|| value.clone());
     }
+    apply_defaults(&mut node.children, &defaults.children);
 }
 
 /// Maps `Vec<`[`KdlNode`]`>` to `Vec<`[`Node`]`>`.
@@ -0,0 +1,170 @@
+//! Redacting values while serializing a [`Node`].
+
+use std::{collections::HashMap, fmt};
+
+use kdl::{KdlNode, KdlValue};
+use serde::{Serialize, Serializer};
+
+use crate::{node, value, Node, Value};
+
+/// The string that [`Redaction::Mask`] replaces values with.
+pub const MASK: &str = "***";
+
+/// What to do with a value, as decided by a redaction hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    /// Serialize the value as is.
+    Keep,
+    /// Serialize [`MASK`] in place of the value.
+    Mask,
+    /// Leave the value out.
+    Drop,
+}
+
+/// Where a value passed to a redaction hook is located.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Path<'a> {
+    /// Names of the nodes from the redacted node down to the one holding the value.
+    pub nodes: &'a [&'a str],
+    /// The entry of that node holding the value.
+    pub entry: Entry<'a>,
+}
+
+/// An entry of a node holding a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry<'a> {
+    /// The value at this index.
+    Argument(usize),
+    /// The property with this name.
+    Property(&'a str),
+}
+
+/// Serializes a [`Node`] like the node itself,
+/// passing every value through a redaction hook first.
+///
+/// Created by [`Node::redacted`]; the node itself is left untouched.
+/// Its [`Display`](fmt::Display) form writes the redacted node as KDL,
+/// and [`Redacted::to_node`] builds a redacted copy.
+pub struct Redacted<'a, F> {
+    node: &'a KdlNode,
+    hook: &'a F,
+    path: Vec<&'a str>,
+}
+
+impl<'a, F> Redacted<'a, F>
+where
+    F: Fn(&Path, &Value) -> Redaction,
+{
+    pub(crate) fn new(node: &'a Node, hook: &'a F) -> Self {
+        Redacted {
+            node: &node.0,
+            hook,
+            path: vec![node.0.name.as_str()],
+        }
+    }
+
+    /// Builds a redacted copy of the node, for example to write it as KDL.
+    ///
+    /// ```rust
+    /// # use serde_kdl::{redact::{Entry, Path, Redaction}, Node, Value};
+    /// fn hide_secrets(path: &Path, _value: &Value) -> Redaction {
+    ///     match path.entry {
+    ///         Entry::Property("password") => Redaction::Mask,
+    ///         Entry::Property("token") => Redaction::Drop,
+    ///         _ => Redaction::Keep,
+    ///     }
+    /// }
+    ///
+    /// let nodes = kdl::parse_document(r#"db { auth password="hunter2" token="t0k3n"; }"#).unwrap();
+    /// let node = Node(nodes[0].clone());
+    /// let redacted = node.redacted(&hide_secrets).to_node();
+    /// assert_eq!(redacted.to_string(), "db {\n    auth password=\"***\"\n}");
+    /// assert_eq!(node.redacted(&hide_secrets).to_string(), redacted.to_string());
+    /// ```
+    pub fn to_node(&self) -> KdlNode {
+        KdlNode {
+            name: self.node.name.clone(),
+            values: value::into_kdl_vec(self.values()),
+            properties: self
+                .properties()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.0))
+                .collect(),
+            children: self.children().map(|child| child.to_node()).collect(),
+        }
+    }
+
+    fn values(&self) -> Vec<Value> {
+        self.node
+            .values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| self.redact(Entry::Argument(i), value))
+            .collect()
+    }
+
+    fn properties(&self) -> HashMap<&'a str, Value> {
+        self.node
+            .properties
+            .iter()
+            .filter_map(|(key, value)| {
+                self.redact(Entry::Property(key), value)
+                    .map(|value| (key.as_str(), value))
+            })
+            .collect()
+    }
+
+    fn children(&self) -> impl Iterator<Item = Redacted<'a, F>> + '_ {
+        self.node.children.iter().map(move |child| {
+            let mut path = self.path.clone();
+            path.push(&child.name);
+            Redacted {
+                node: child,
+                hook: self.hook,
+                path,
+            }
+        })
+    }
+
+    fn redact(&self, entry: Entry, value: &KdlValue) -> Option<Value> {
+        let path = Path {
+            nodes: &self.path,
+            entry,
+        };
+        let value = Value(value.clone());
+        match (self.hook)(&path, &value) {
+            Redaction::Keep => Some(value),
+            Redaction::Mask => Some(Value(KdlValue::String(MASK.to_string()))),
+            Redaction::Drop => None,
+        }
+    }
+}
+
+/// Writes the redacted node as KDL.
+impl<'a, F> fmt::Display for Redacted<'a, F>
+where
+    F: Fn(&Path, &Value) -> Redaction,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_node().fmt(f)
+    }
+}
+
+impl<'a, F> Serialize for Redacted<'a, F>
+where
+    F: Fn(&Path, &Value) -> Redaction,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let children: Vec<Redacted<F>> = self.children().collect();
+        node::serialize_node(
+            serializer,
+            &self.node.name,
+            &self.values(),
+            &self.properties(),
+            &children,
+        )
+    }
+}
@@ -0,0 +1,199 @@
+//! Helpers for comparing KDL documents in tests.
+
+use std::collections::BTreeSet;
+
+use kdl::KdlNode;
+
+use crate::{
+    align::align,
+    display::{self, Siblings},
+    Node,
+};
+
+/// Asserts that two KDL documents are semantically equal.
+///
+/// Both sides can be anything implementing [`IntoNodes`],
+/// such as KDL source text or already parsed nodes.
+/// Property order and formatting are ignored;
+/// on failure, the panic message lists every structural difference.
+///
+/// ```rust
+/// # use serde_kdl::assert_kdl_eq;
+/// assert_kdl_eq!(
+///     "server port=80 host=\"localhost\" { tls; }",
+///     r#"
+///     server host="localhost" port=80 {
+///         tls
+///     }
+///     "#
+/// );
+/// ```
+///
+/// ```rust,should_panic
+/// # use serde_kdl::assert_kdl_eq;
+/// // server[0]: property `port`: `80` != `8080`
+/// assert_kdl_eq!("server port=80", "server port=8080");
+/// ```
+#[macro_export]
+macro_rules! assert_kdl_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_eq($left, $right)
+    };
+}
+
+/// Conversion into a list of nodes to compare, used by [`assert_kdl_eq!`].
+pub trait IntoNodes {
+    /// Converts `self` into a list of top-level nodes.
+    ///
+    /// Panics if `self` is not a valid KDL document.
+    fn into_nodes(self) -> Vec<KdlNode>;
+}
+
+impl IntoNodes for &str {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        kdl::parse_document(self).unwrap_or_else(|e| panic!("invalid KDL document: {}", e))
+    }
+}
+
+impl IntoNodes for &String {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        self.as_str().into_nodes()
+    }
+}
+
+impl IntoNodes for String {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        self.as_str().into_nodes()
+    }
+}
+
+impl IntoNodes for Vec<KdlNode> {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        self
+    }
+}
+
+impl IntoNodes for &[KdlNode] {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        self.to_vec()
+    }
+}
+
+impl IntoNodes for KdlNode {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        vec![self]
+    }
+}
+
+impl IntoNodes for Node {
+    fn into_nodes(self) -> Vec<KdlNode> {
+        vec![self.0]
+    }
+}
+
+/// Panics with a structural diff if `left` and `right` are not equal.
+///
+/// This is the function behind [`assert_kdl_eq!`].
+#[track_caller]
+pub fn assert_eq(left: impl IntoNodes, right: impl IntoNodes) {
+    let differences = diff(&left.into_nodes(), &right.into_nodes());
+    if !differences.is_empty() {
+        panic!(
+            "assertion failed: `(left == right)` for KDL documents\n  {}",
+            differences.join("\n  ")
+        );
+    }
+}
+
+/// Lists the differences between two lists of nodes, one per line.
+///
+/// Siblings are lined up like in [`merge`](crate::merge), so inserted
+/// and removed nodes show up as such rather than shifting the ones after them.
+/// Paths look like `a[0]/b[1]`, where the index counts the preceding siblings
+/// with the same name, on the left unless the node is only on the right.
+///
+/// ```rust
+/// # use serde_kdl::testing::{diff, IntoNodes};
+/// let left = "a 1 { b x=1; }".into_nodes();
+/// let right = "a 2 { b; c; }".into_nodes();
+/// assert_eq!(
+///     diff(&left, &right),
+///     vec![
+///         "a[0]: arguments `1` != `2`",
+///         "a[0]/b[0]: property `x` only on the left (`1`)",
+///         "a[0]/c[0]: node only on the right",
+///     ]
+/// );
+///
+/// let left = "a; b; c; d".into_nodes();
+/// let right = "x; a; b; c; d".into_nodes();
+/// assert_eq!(diff(&left, &right), vec!["x[0]: node only on the right"]);
+/// ```
+pub fn diff(left: &[KdlNode], right: &[KdlNode]) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_children("", left, right, &mut differences);
+    differences
+}
+
+fn diff_children(parent: &str, left: &[KdlNode], right: &[KdlNode], out: &mut Vec<String>) {
+    let alignment = align(left, right);
+    let (mut left_siblings, mut right_siblings) = (Siblings::new(parent), Siblings::new(parent));
+    for i in 0..=left.len() {
+        for r in &alignment.added[i] {
+            out.push(format!(
+                "{}: node only on the right",
+                right_siblings.path(&r.name)
+            ));
+            right_siblings.count(&r.name);
+        }
+        let Some(l) = left.get(i) else {
+            break;
+        };
+        match alignment.matches[i] {
+            Some(r) => {
+                diff_node(&left_siblings.path(&l.name), l, r, out);
+                right_siblings.count(&r.name);
+            }
+            None => out.push(format!(
+                "{}: node only on the left",
+                left_siblings.path(&l.name)
+            )),
+        }
+        left_siblings.count(&l.name);
+    }
+}
+
+fn diff_node(path: &str, left: &KdlNode, right: &KdlNode, out: &mut Vec<String>) {
+    if left.values != right.values {
+        out.push(format!(
+            "{}: arguments `{}` != `{}`",
+            path,
+            display::values(&left.values),
+            display::values(&right.values)
+        ));
+    }
+
+    let keys: BTreeSet<_> = left
+        .properties
+        .keys()
+        .chain(right.properties.keys())
+        .collect();
+    for key in keys {
+        match (left.properties.get(key), right.properties.get(key)) {
+            (Some(l), Some(r)) if l != r => {
+                out.push(format!("{}: property `{}`: `{}` != `{}`", path, key, l, r))
+            }
+            (Some(l), None) => out.push(format!(
+                "{}: property `{}` only on the left (`{}`)",
+                path, key, l
+            )),
+            (None, Some(r)) => out.push(format!(
+                "{}: property `{}` only on the right (`{}`)",
+                path, key, r
+            )),
+            _ => {}
+        }
+    }
+
+    diff_children(path, &left.children, &right.children, out);
+}
@@ -0,0 +1,76 @@
+//! Node spans for the `tracing` feature.
+
+use std::cell::RefCell;
+
+use tracing::{span::EnteredSpan, Span};
+
+thread_local! {
+    /// Names and spans of the nodes being processed on this thread, outermost first.
+    static PATH: RefCell<Vec<(String, Span)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Enters a span with `node` and `path` fields for the node being processed.
+///
+/// `$node` is an `Option<&str>`: pass `None` if the name isn't known yet,
+/// and fill it in later with [`name_current_node`].
+macro_rules! node_span {
+    ($level:ident, $name:literal, $node:expr) => {
+        $crate::trace::NodeSpan::enter(
+            tracing::span!(
+                tracing::Level::$level,
+                $name,
+                node = tracing::field::Empty,
+                path = tracing::field::Empty
+            ),
+            $node,
+        )
+    };
+}
+
+pub(crate) use node_span;
+
+/// Keeps a node span entered, and the node on the path, until dropped.
+pub(crate) struct NodeSpan {
+    _span: EnteredSpan,
+}
+
+impl NodeSpan {
+    pub(crate) fn enter(span: Span, node: Option<&str>) -> Self {
+        PATH.with(|path| path.borrow_mut().push((String::from("?"), span.clone())));
+        let span = NodeSpan {
+            _span: span.entered(),
+        };
+        if let Some(node) = node {
+            name_current_node(node);
+        }
+        span
+    }
+}
+
+impl Drop for NodeSpan {
+    fn drop(&mut self) {
+        PATH.with(|path| path.borrow_mut().pop());
+    }
+}
+
+/// Records the name of the innermost node being processed, and its path, on its span.
+///
+/// The span is looked up on the path rather than being the current span,
+/// which is some outer span when the node span is disabled.
+pub(crate) fn name_current_node(node: &str) {
+    PATH.with(|path| {
+        let mut path = path.borrow_mut();
+        let Some((name, span)) = path.last_mut() else {
+            return;
+        };
+        *name = node.to_string();
+        let span = span.clone();
+        let joined = path
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join("/");
+        span.record("node", node);
+        span.record("path", joined.as_str());
+    });
+}
@@ -1,7 +1,14 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, convert::TryFrom, fmt};
 
 use kdl::KdlValue;
-use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{self, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, Impossible},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::Error;
 
 /// A newtype wrapper for [`KdlValue`] with Serde support.
 ///
@@ -62,6 +69,16 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(Value(KdlValue::Int(i64::from(value))))
             }
 
+            #[inline]
+            fn visit_u64<E>(self, value: u64) -> Result<Value, E>
+            where
+                E: serde::de::Error,
+            {
+                i64::try_from(value)
+                    .map(|i| Value(KdlValue::Int(i)))
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
             #[inline]
             fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
                 Ok(Value(KdlValue::Float(value)))
@@ -105,6 +122,269 @@ impl<'de> Deserialize<'de> for Value {
     }
 }
 
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            KdlValue::Null => visitor.visit_unit(),
+            KdlValue::Boolean(b) => visitor.visit_bool(b),
+            KdlValue::Int(i) => visitor.visit_i64(i),
+            KdlValue::Float(f) => visitor.visit_f64(f),
+            KdlValue::String(s) => visitor.visit_string(s),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            KdlValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            KdlValue::String(s) => visitor.visit_enum(s.into_deserializer()),
+            other => Err(de::Error::invalid_type(unexpected(&other), &visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn unexpected(value: &KdlValue) -> de::Unexpected<'_> {
+    match *value {
+        KdlValue::Null => de::Unexpected::Unit,
+        KdlValue::Boolean(b) => de::Unexpected::Bool(b),
+        KdlValue::Int(i) => de::Unexpected::Signed(i),
+        KdlValue::Float(f) => de::Unexpected::Float(f),
+        KdlValue::String(ref s) => de::Unexpected::Str(s),
+    }
+}
+
+/// Serializes `value` into a single [`KdlValue`].
+///
+/// Only scalars can be represented: booleans, numbers, strings, units,
+/// options and unit enum variants (as their names).
+pub(crate) fn to_kdl_value<T>(value: &T) -> Result<KdlValue, Error>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)
+}
+
+struct ValueSerializer;
+
+impl ValueSerializer {
+    fn not_scalar(kind: &str) -> Error {
+        ser::Error::custom(format_args!("{} cannot be a KDL value", kind))
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = KdlValue;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<KdlValue, Error>;
+    type SerializeTuple = Impossible<KdlValue, Error>;
+    type SerializeTupleStruct = Impossible<KdlValue, Error>;
+    type SerializeTupleVariant = Impossible<KdlValue, Error>;
+    type SerializeMap = Impossible<KdlValue, Error>;
+    type SerializeStruct = Impossible<KdlValue, Error>;
+    type SerializeStructVariant = Impossible<KdlValue, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<KdlValue, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<KdlValue, Error> {
+        i64::try_from(v)
+            .map(KdlValue::Int)
+            .map_err(|_| ser::Error::custom(format_args!("integer `{}` is out of range", v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<KdlValue, Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<KdlValue, Error> {
+        Ok(KdlValue::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<KdlValue, Error> {
+        Ok(KdlValue::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<KdlValue, Error> {
+        Err(Self::not_scalar("a byte array"))
+    }
+
+    fn serialize_none(self) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Null)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<KdlValue, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<KdlValue, Error> {
+        Ok(KdlValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<KdlValue, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<KdlValue, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<KdlValue, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Self::not_scalar("a newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Self::not_scalar("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Self::not_scalar("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Self::not_scalar("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Self::not_scalar("a tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Self::not_scalar("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(Self::not_scalar("a struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Self::not_scalar("a struct variant"))
+    }
+}
+
 /// Maps `Vec<`[`KdlValue`]`>` to `Vec<`[`Value`]`>`.
 pub fn from_kdl_vec(v: Vec<KdlValue>) -> Vec<Value> {
     v.into_iter().map(Value).collect()
@@ -0,0 +1,177 @@
+//! Modules for use with `#[serde(with = "...")]` that encode common KDL idioms.
+
+/// (De)serializes a `Vec<T>` as a single comma-separated string,
+/// like `tags="web,db,cache"`.
+///
+/// Reading trims whitespace around items and turns a blank string into an empty `Vec`,
+/// so items must not be empty, start or end with whitespace, or contain commas,
+/// as they wouldn't read back the same. Serializing such an item fails.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_json::json;
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Service {
+///     #[serde(with = "serde_kdl::with::comma_separated")]
+///     ports: Vec<u16>,
+/// }
+///
+/// let service = Service { ports: vec![80, 443] };
+/// let json = serde_json::to_value(&service).unwrap();
+/// assert_eq!(json, json!({ "ports": "80,443" }));
+///
+/// let service: Service = serde_json::from_value(json!({ "ports": "80, 443" })).unwrap();
+/// assert_eq!(service.ports, vec![80, 443]);
+///
+/// #[derive(Serialize)]
+/// struct Tags {
+///     #[serde(with = "serde_kdl::with::comma_separated")]
+///     tags: Vec<String>,
+/// }
+///
+/// for tags in [vec!["web", "db,cache"], vec![""], vec![" web "]] {
+///     let tags = Tags { tags: tags.into_iter().map(String::from).collect() };
+///     assert!(serde_json::to_value(&tags).is_err());
+/// }
+/// ```
+pub mod comma_separated {
+    use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
+    use serde::{
+        de::{self, Visitor},
+        ser, Deserializer, Serializer,
+    };
+
+    /// Serializes `values` as their [`Display`] forms joined with commas.
+    ///
+    /// Fails if any item is empty, starts or ends with whitespace, or contains a comma.
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        let items = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        for item in &items {
+            let problem = if item.trim().is_empty() {
+                "is blank"
+            } else if item.trim() != item {
+                "has whitespace around it"
+            } else if item.contains(',') {
+                "contains a comma"
+            } else {
+                continue;
+            };
+            return Err(ser::Error::custom(format_args!(
+                "item `{}` {}",
+                item, problem
+            )));
+        }
+        serializer.serialize_str(&items.join(","))
+    }
+
+    /// Deserializes a string of comma-separated items, parsing each with [`FromStr`].
+    ///
+    /// Whitespace around items is ignored, and an empty string gives an empty `Vec`.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        struct CommaSeparatedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for CommaSeparatedVisitor<T>
+        where
+            T: FromStr,
+            T::Err: Display,
+        {
+            type Value = Vec<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a comma-separated string")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Vec<T>, E>
+            where
+                E: de::Error,
+            {
+                if value.trim().is_empty() {
+                    return Ok(Vec::new());
+                }
+                value
+                    .split(',')
+                    .map(|item| item.trim().parse().map_err(de::Error::custom))
+                    .collect()
+            }
+        }
+
+        deserializer.deserialize_str(CommaSeparatedVisitor(PhantomData))
+    }
+}
+
+/// (De)serializes a `HashMap<String, KdlNode>` as a list of [`Node`](crate::Node)s,
+/// using each key as the name of its node.
+///
+/// ```rust
+/// # use std::collections::HashMap;
+/// # use kdl::KdlNode;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "serde_kdl::with::node_name_map")]
+///     servers: HashMap<String, KdlNode>,
+/// }
+///
+/// let mut servers = HashMap::new();
+/// for node in kdl::parse_document("alpha port=80; beta port=8080").unwrap() {
+///     servers.insert(node.name.clone(), node);
+/// }
+/// let config = Config { servers };
+///
+/// let json = serde_json::to_value(&config).unwrap();
+/// assert_eq!(json["servers"][1]["name"], "beta");
+/// assert_eq!(serde_json::from_value::<Config>(json).unwrap(), config);
+/// ```
+pub mod node_name_map {
+    use std::collections::HashMap;
+
+    use kdl::KdlNode;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::Node;
+
+    /// Serializes `nodes` as a sequence of nodes named after their keys, sorted by name.
+    pub fn serialize<S>(nodes: &HashMap<String, KdlNode>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut names: Vec<_> = nodes.keys().collect();
+        names.sort();
+        serializer.collect_seq(names.into_iter().map(|name| {
+            Node(KdlNode {
+                name: name.clone(),
+                ..nodes[name].clone()
+            })
+        }))
+    }
+
+    /// Deserializes a sequence of nodes into a map keyed by their names.
+    ///
+    /// Fails if two nodes share a name.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<String, KdlNode>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut nodes = HashMap::new();
+        for Node(node) in Vec::<Node>::deserialize(deserializer)? {
+            if nodes.contains_key(&node.name) {
+                return Err(de::Error::custom(format_args!(
+                    "duplicate node `{}`",
+                    node.name
+                )));
+            }
+            nodes.insert(node.name.clone(), node);
+        }
+        Ok(nodes)
+    }
+}
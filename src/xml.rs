@@ -0,0 +1,225 @@
+//! Conversions between XML elements and [`KdlNode`]s.
+//!
+//! Elements map to nodes of the same name, attributes to string properties,
+//! text to string values and child elements to children:
+//!
+//! ```text
+//! <server host="localhost">             server "web" host="localhost" {
+//!     web                          <->      tls enabled="true"
+//!     <tls enabled="true"/>             }
+//! </server>
+//! ```
+//!
+//! XML has no value types, so everything read from XML is a string.
+//! Whitespace around text is trimmed when reading, and text between child elements
+//! reads as separate values, which are all written before the children,
+//! so mixed content like `<a>x<b/>y</a>` is written back as `<a>x y<b/></a>`.
+//!
+//! Values are written separated by spaces in the element text,
+//! which reads back as a single value to be split on whitespace.
+//! Writing fails for anything that couldn't be read back:
+//! node and property names that aren't valid XML names,
+//! values that are empty or have whitespace around them,
+//! and values containing whitespace next to other values.
+
+use std::{borrow::Cow, fmt, str};
+
+use kdl::{KdlNode, KdlValue};
+use quick_xml::{
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use serde::{de, ser};
+
+use crate::Error;
+
+/// Parses XML into a list of nodes, one for each root element.
+///
+/// ```rust
+/// # use kdl::KdlValue;
+/// let xml = r#"<server host="localhost">web<tls enabled="true"/></server>"#;
+/// let nodes = serde_kdl::xml::from_str(xml).unwrap();
+/// assert_eq!(nodes[0].values, vec![KdlValue::String("web".to_string())]);
+/// assert_eq!(nodes[0].children[0].to_string(), r#"tls enabled="true""#);
+///
+/// let nodes = serde_kdl::xml::from_str("<a>x<b/>y</a>").unwrap();
+/// assert_eq!(serde_kdl::xml::to_string(&nodes).unwrap(), "<a>x y<b/></a>");
+/// ```
+pub fn from_str(xml: &str) -> Result<Vec<KdlNode>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_xml", len = xml.len()).entered();
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<KdlNode> = Vec::new();
+    loop {
+        match reader.read_event().map_err(de::Error::custom)? {
+            Event::Start(start) => stack.push(element(&start)?),
+            Event::Empty(start) => append(&mut stack, &mut roots, element(&start)?),
+            Event::End(_) => {
+                let node = stack.pop().expect("end tags are checked by the reader");
+                append(&mut stack, &mut roots, node);
+            }
+            Event::Text(text) => {
+                push_text(&mut stack, text.unescape().map_err(de::Error::custom)?)?
+            }
+            Event::CData(data) => {
+                let text = str::from_utf8(&data).map_err(de::Error::custom)?;
+                push_text(&mut stack, Cow::Borrowed(text))?
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+
+    match stack.pop() {
+        Some(node) => Err(de::Error::custom(format_args!(
+            "unclosed element `{}`",
+            node.name
+        ))),
+        None => Ok(roots),
+    }
+}
+
+/// Writes nodes as compact XML, one root element for each node.
+///
+/// ```rust
+/// let nodes = kdl::parse_document(r#"server "web" { tls enabled=true; }"#).unwrap();
+/// assert_eq!(
+///     serde_kdl::xml::to_string(&nodes).unwrap(),
+///     r#"<server>web<tls enabled="true"/></server>"#
+/// );
+///
+/// let nodes = kdl::parse_document(r#""web server"; hosts "a b" "c"; name ""; name " a ""#).unwrap();
+/// let err = serde_kdl::xml::to_string(&nodes[..1]).unwrap_err();
+/// assert_eq!(err.to_string(), "node `web server`: `web server` is not a valid XML name");
+/// for node in &nodes[1..] {
+///     assert!(serde_kdl::xml::to_string(std::slice::from_ref(node)).is_err());
+/// }
+/// ```
+pub fn to_string(nodes: &[KdlNode]) -> Result<String, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("write_xml", nodes = nodes.len()).entered();
+
+    let mut writer = Writer::new(Vec::new());
+    for node in nodes {
+        write_node(&mut writer, node)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()).expect("XML is written from strings"))
+}
+
+fn element(start: &BytesStart) -> Result<KdlNode, Error> {
+    let name = str::from_utf8(start.name().as_ref())
+        .map_err(de::Error::custom)?
+        .to_string();
+    let mut node = KdlNode {
+        name,
+        ..Default::default()
+    };
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(de::Error::custom)?;
+        let key = str::from_utf8(attribute.key.as_ref()).map_err(de::Error::custom)?;
+        let value = attribute.unescape_value().map_err(de::Error::custom)?;
+        node.properties
+            .insert(key.to_string(), KdlValue::String(value.into_owned()));
+    }
+    Ok(node)
+}
+
+fn append(stack: &mut [KdlNode], roots: &mut Vec<KdlNode>, node: KdlNode) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+fn push_text(stack: &mut [KdlNode], text: Cow<'_, str>) -> Result<(), Error> {
+    match stack.last_mut() {
+        Some(node) => {
+            node.values.push(KdlValue::String(text.into_owned()));
+            Ok(())
+        }
+        None => Err(de::Error::custom(format_args!(
+            "text `{}` outside of an element",
+            text
+        ))),
+    }
+}
+
+fn write_node(writer: &mut Writer<Vec<u8>>, node: &KdlNode) -> Result<(), Error> {
+    check_name(&node.name).map_err(|e| write_error(node, e))?;
+    let mut properties: Vec<_> = node.properties.iter().collect();
+    properties.sort_by_key(|(key, _)| *key);
+
+    let mut start = BytesStart::new(node.name.as_str());
+    for (key, value) in properties {
+        check_name(key)
+            .map_err(|e| write_error(node, format_args!("property `{}`: {}", key, e)))?;
+        start.push_attribute((key.as_str(), text(value).as_ref()));
+    }
+
+    if node.values.is_empty() && node.children.is_empty() {
+        write(writer, Event::Empty(start));
+        return Ok(());
+    }
+
+    write(writer, Event::Start(start));
+    if !node.values.is_empty() {
+        let values: Vec<_> = node.values.iter().map(text).collect();
+        for value in &values {
+            let problem = if value.is_empty() {
+                "would read back as no value"
+            } else if value.trim() != value {
+                "would lose the whitespace around it"
+            } else if values.len() > 1 && value.contains(char::is_whitespace) {
+                "couldn't be separated from the other values"
+            } else {
+                continue;
+            };
+            return Err(write_error(
+                node,
+                format_args!("value `{}` {}", value, problem),
+            ));
+        }
+        write(writer, Event::Text(BytesText::new(&values.join(" "))));
+    }
+    for child in &node.children {
+        write_node(writer, child)?;
+    }
+    write(writer, Event::End(BytesEnd::new(node.name.as_str())));
+    Ok(())
+}
+
+fn write_error(node: &KdlNode, msg: impl fmt::Display) -> Error {
+    <Error as ser::Error>::custom(msg).in_node(&node.name)
+}
+
+/// Checks that `name` is a valid XML element or attribute name.
+fn check_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a valid XML name", name))
+    }
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) {
+    writer
+        .write_event(event)
+        .expect("writing to a Vec cannot fail");
+}
+
+/// Formats a value as XML text, without the quotes around strings.
+fn text(value: &KdlValue) -> Cow<'_, str> {
+    match value {
+        KdlValue::String(s) => Cow::Borrowed(s),
+        other => Cow::Owned(other.to_string()),
+    }
+}